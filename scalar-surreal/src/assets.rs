@@ -0,0 +1,334 @@
+use scalar::{DateTime, Document, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use surrealdb::{sql::Thing, Connection};
+
+use crate::SurrealConnection;
+
+fn thing_to_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let t = Thing::deserialize(deserializer)?;
+    Ok(t.id.to_raw())
+}
+
+fn optional_thing_to_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let thing = Option::<Thing>::deserialize(deserializer)?;
+    Ok(thing.map(|t| t.id.to_raw()))
+}
+
+/// A reference to a stored [`Asset`], held by a `Document` field. Serializes
+/// as just the asset's id, so it round-trips through `inner`'s flexible
+/// JSON like any other scalar; [`crate::query::searchable_fields`]'s sibling
+/// [`asset_fields`] is what lets reads `FETCH` it back into a full [`Asset`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AssetRef(String);
+
+impl AssetRef {
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Metadata recorded in `sc__asset` for every upload, regardless of which
+/// [`AssetBackend`] its bytes actually live in.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Asset {
+    #[serde(deserialize_with = "thing_to_string")]
+    pub id: String,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: i64,
+    pub checksum: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, deserialize_with = "optional_thing_to_string")]
+    pub uploaded_by: Option<String>,
+}
+
+/// Where [`SurrealConnection::get_asset`] sends a caller to read an asset's
+/// bytes: either the bytes themselves, or a URL they can fetch directly.
+pub enum AssetLocation {
+    Bytes(Vec<u8>),
+    Url(String),
+}
+
+/// Where an [`Asset`]'s bytes actually live. `sc__asset` always holds the
+/// metadata above; this trait is just the storage/retrieval of the bytes
+/// themselves, so the crate isn't tied to one object store.
+pub trait AssetBackend: Send + Sync {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    async fn store(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), Self::Error>;
+
+    /// Returns `key`'s bytes, or a URL the caller can fetch them from
+    /// directly instead of streaming them through this process.
+    async fn retrieve(&self, key: &str) -> Result<AssetLocation, Self::Error>;
+}
+
+/// Stores asset bytes directly in SurrealDB, as a `bytes` field on
+/// `sc__asset_blob`. Simplest option; best for deployments that don't want
+/// a separate object store.
+pub struct SurrealBlobBackend<C: Connection> {
+    connection: SurrealConnection<C>,
+}
+
+impl<C: Connection> SurrealBlobBackend<C> {
+    pub fn new(connection: SurrealConnection<C>) -> Self {
+        Self { connection }
+    }
+}
+
+impl<C: Connection> AssetBackend for SurrealBlobBackend<C> {
+    type Error = surrealdb::Error;
+
+    async fn store(&self, key: &str, bytes: &[u8], _content_type: &str) -> Result<(), Self::Error> {
+        self.connection
+            .query("UPSERT type::thing('sc__asset_blob', $key) SET bytes = $bytes")
+            .bind(("key", key.to_owned()))
+            .bind(("bytes", bytes.to_vec()))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<AssetLocation, Self::Error> {
+        let bytes: Option<Vec<u8>> = self
+            .connection
+            .query("SELECT VALUE bytes FROM type::thing('sc__asset_blob', $key)")
+            .bind(("key", key.to_owned()))
+            .await?
+            .take(0)?;
+
+        Ok(AssetLocation::Bytes(bytes.unwrap_or_default()))
+    }
+}
+
+/// The minimal surface an S3-compatible client needs to expose for
+/// [`S3Backend`]. Implement this against whichever SDK an application
+/// already depends on; this crate doesn't pull one in itself.
+pub trait S3Client: Send + Sync {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        bytes: &[u8],
+        content_type: &str,
+    ) -> Result<(), Self::Error>;
+
+    /// A presigned URL the caller can `GET` directly, valid for
+    /// `expires_in_secs` seconds.
+    fn presigned_get_url(&self, bucket: &str, key: &str, expires_in_secs: u32) -> String;
+}
+
+/// Stores asset bytes in an S3-compatible object store, under
+/// `{key_prefix}/{asset id}`. Retrieval returns a presigned URL instead of
+/// streaming bytes through this process.
+pub struct S3Backend<T: S3Client> {
+    client: T,
+    bucket: String,
+    key_prefix: String,
+}
+
+impl<T: S3Client> S3Backend<T> {
+    pub fn new(client: T, bucket: impl Into<String>, key_prefix: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            key_prefix: key_prefix.into(),
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}/{key}", self.key_prefix.trim_end_matches('/'))
+    }
+}
+
+impl<T: S3Client> AssetBackend for S3Backend<T> {
+    type Error = T::Error;
+
+    async fn store(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<(), Self::Error> {
+        self.client
+            .put_object(&self.bucket, &self.object_key(key), bytes, content_type)
+            .await
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<AssetLocation, Self::Error> {
+        Ok(AssetLocation::Url(self.client.presigned_get_url(
+            &self.bucket,
+            &self.object_key(key),
+            3600,
+        )))
+    }
+}
+
+/// An error from [`SurrealConnection::put_asset`] or
+/// [`SurrealConnection::get_asset`]: either the `sc__asset` metadata
+/// round-trip failed, or the backend storing/retrieving the bytes did.
+#[derive(Debug)]
+pub enum AssetError<E> {
+    Database(surrealdb::Error),
+    Backend(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for AssetError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "{e}"),
+            Self::Backend(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for AssetError<E> {}
+
+impl<E> From<surrealdb::Error> for AssetError<E> {
+    fn from(value: surrealdb::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// The names of the fields a `Document` marks as holding an [`AssetRef`] in
+/// its [`Document::fields`] schema, i.e. those with `"asset": true`.
+pub(crate) fn asset_fields<D: Document>() -> Vec<String> {
+    let schema = serde_json::to_value(D::fields()).unwrap_or(serde_json::Value::Null);
+    let mut fields = Vec::new();
+
+    if let serde_json::Value::Array(entries) = schema {
+        for entry in entries {
+            let is_asset = entry
+                .get("asset")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            if let Some(name) = is_asset
+                .then(|| entry.get("name"))
+                .flatten()
+                .and_then(|v| v.as_str())
+            {
+                fields.push(name.to_owned());
+            }
+        }
+    }
+
+    fields
+}
+
+/// Builds a `FETCH` clause resolving `base` (e.g. `draft`, `published`)
+/// plus every `D::{scope}.{field}` where `field` is an [`AssetRef`] field
+/// `D` declares, for each `scope` in `asset_scopes` (e.g. `draft.inner`,
+/// `published.inner`, or just `inner` when querying a published table
+/// directly), so those come back as the full `sc__asset` record instead of
+/// a bare id. Returns `None` if there'd be nothing to `FETCH`, since an
+/// empty `FETCH` clause isn't valid SurrealQL.
+pub(crate) fn fetch_clause<D: Document>(base: &[&str], asset_scopes: &[&str]) -> Option<String> {
+    let mut targets: Vec<String> = base.iter().map(|s| s.to_string()).collect();
+    let asset_fields = asset_fields::<D>();
+
+    for scope in asset_scopes {
+        for field in &asset_fields {
+            targets.push(format!("{scope}.{field}"));
+        }
+    }
+
+    (!targets.is_empty()).then(|| format!("FETCH {}", targets.join(", ")))
+}
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Stores `bytes` via `backend`, recording its metadata in `sc__asset`.
+    /// Identical uploads are deduplicated by checksum: re-uploading the
+    /// same bytes returns the existing asset's ref instead of storing (and
+    /// paying for) a second copy.
+    pub async fn put_asset<B: AssetBackend>(
+        &self,
+        backend: &B,
+        bytes: &[u8],
+        filename: Option<&str>,
+        content_type: &str,
+    ) -> Result<AssetRef, AssetError<B::Error>> {
+        // The checksum doubles as the asset's id: dedup becomes a direct id
+        // lookup, and it lets us store the bytes before recording any
+        // metadata, so a failed `backend.store` can't leave an `sc__asset`
+        // row whose bytes were never written (which dedup would otherwise
+        // hand back forever). Unlike the migration checksums, this one has
+        // to be collision-resistant, since a collision here aliases two
+        // distinct uploads onto the same asset — so SHA-256 via SurrealDB's
+        // own `crypto::sha256`, not the non-cryptographic `fnv1a64`.
+        let checksum: Option<String> = self
+            .query("RETURN crypto::sha256($bytes)")
+            .bind(("bytes", bytes.to_vec()))
+            .await?
+            .take(0)?;
+        let checksum = checksum.expect("crypto::sha256 always returns a value");
+
+        #[derive(Deserialize)]
+        struct Existing {
+            #[serde(deserialize_with = "thing_to_string")]
+            id: String,
+        }
+
+        let existing: Option<Existing> = self
+            .query("SELECT id FROM type::thing('sc__asset', $checksum)")
+            .bind(("checksum", checksum.clone()))
+            .await?
+            .take(0)?;
+
+        if let Some(existing) = existing {
+            return Ok(AssetRef(existing.id));
+        }
+
+        backend
+            .store(&checksum, bytes, content_type)
+            .await
+            .map_err(AssetError::Backend)?;
+
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            checksum: String,
+            filename: Option<&'a str>,
+            content_type: &'a str,
+            size: i64,
+        }
+
+        self.query("CREATE type::thing('sc__asset', $checksum) SET filename = $filename, content_type = $content_type, size = $size, checksum = $checksum, uploaded_by = $auth.id")
+            .bind(Bindings {
+                checksum: checksum.clone(),
+                filename,
+                content_type,
+                size: bytes.len() as i64,
+            })
+            .await?;
+
+        Ok(AssetRef(checksum))
+    }
+
+    /// Looks up `asset`'s metadata in `sc__asset`, then asks `backend` for
+    /// its bytes (or a URL to them).
+    pub async fn get_asset<B: AssetBackend>(
+        &self,
+        backend: &B,
+        asset: &AssetRef,
+    ) -> Result<Option<(Asset, AssetLocation)>, AssetError<B::Error>> {
+        let meta: Option<Asset> = self
+            .query("SELECT id, filename, content_type, size, checksum, created_at, uploaded_by FROM type::thing('sc__asset', $id)")
+            .bind(("id", asset.0.clone()))
+            .await?
+            .take(0)?;
+
+        let Some(meta) = meta else {
+            return Ok(None);
+        };
+
+        let location = backend
+            .retrieve(&asset.0)
+            .await
+            .map_err(AssetError::Backend)?;
+
+        Ok(Some((meta, location)))
+    }
+}
@@ -0,0 +1,114 @@
+use scalar::Document;
+use surrealdb::Connection;
+
+use crate::SurrealConnection;
+
+/// One of the four actions SurrealDB table permissions are expressed over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Select,
+    Create,
+    Update,
+    Delete,
+}
+
+impl Action {
+    fn as_surql(self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// A named bundle of [`Action`]s, granted to an editor via a
+/// `"<doc>:<name>"` entry in their `roles` set.
+pub struct Permission {
+    pub name: &'static str,
+    pub actions: &'static [Action],
+}
+
+/// Lets an editor read a document type's published and draft content.
+pub const READ: Permission = Permission {
+    name: "read",
+    actions: &[Action::Select],
+};
+
+/// Lets an editor create, update, and delete a document type's content.
+pub const WRITE: Permission = Permission {
+    name: "write",
+    actions: &[Action::Create, Action::Update, Action::Delete],
+};
+
+/// The permissions every [`Document`] gets out of the box: a read role and
+/// a write role, each scoped to that one document type.
+pub const DEFAULT_PERMISSIONS: &[Permission] = &[READ, WRITE];
+
+/// The `roles` entry that grants `permission` over `doc`, e.g. `"post:write"`.
+pub fn role_for(doc: &str, permission: &Permission) -> String {
+    format!("{doc}:{}", permission.name)
+}
+
+/// Builds the `PERMISSIONS ...` clause of a `DEFINE TABLE` statement for
+/// `doc`, granting each permission's actions to editors holding its role
+/// (or to admins, unconditionally).
+pub fn permissions_clause(doc: &str, permissions: &[Permission]) -> String {
+    permissions
+        .iter()
+        .map(|permission| {
+            let actions = permission
+                .actions
+                .iter()
+                .map(|a| a.as_surql())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let role = role_for(doc, permission);
+
+            format!("FOR {actions} WHERE $auth.admin = true OR $auth.roles CONTAINS '{role}'")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds a meta table's `PERMISSIONS ...` clause: the same role-gated
+/// `FOR create, update, delete` as [`permissions_clause`], but a `FOR
+/// select` that also lets anyone read a row the query layer would show
+/// them anyway — the same visibility check `get_by_id`/`query` apply in
+/// their own `WHERE` clause (has a draft, or is published and not
+/// scheduled for the future, or the caller is authenticated at all).
+/// Without this, the role-gated `SELECT` from [`permissions_clause`] would
+/// reject every unauthenticated read before the query layer's visibility
+/// branch for them ever runs.
+pub fn meta_permissions_clause(doc: &str) -> String {
+    let read_role = role_for(doc, &READ);
+    let write = permissions_clause(doc, &[WRITE]);
+
+    format!(
+        "FOR select WHERE $auth.admin = true OR $auth.roles CONTAINS '{read_role}' OR $auth.id IS NOT NONE OR draft IS NOT NONE OR published.published_at IS NONE OR published.published_at <= time::now() {write}"
+    )
+}
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Whether the signed-in editor may perform `action` on `D`, either via
+    /// the matching role or the `admin` superuser shortcut.
+    pub async fn has_permission<D: Document + Send>(
+        &self,
+        action: Action,
+    ) -> Result<bool, surrealdb::Error> {
+        let permission = if action == Action::Select {
+            &READ
+        } else {
+            &WRITE
+        };
+        let role = role_for(D::identifier(), permission);
+
+        let mut result = self
+            .query("RETURN $auth.admin = true OR $auth.roles CONTAINS $role")
+            .bind(("role", role))
+            .await?;
+
+        Ok(result.take::<Option<bool>>(0)?.unwrap_or(false))
+    }
+}
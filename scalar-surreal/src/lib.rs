@@ -1,4 +1,4 @@
-use std::{borrow::Cow, marker::PhantomData, ops::Deref, sync::Arc};
+use std::{borrow::Cow, ops::Deref, sync::Arc};
 
 use scalar::{
     db::{AuthenticationError, Credentials, DatabaseFactory},
@@ -7,14 +7,33 @@ use scalar::{
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use surrealdb::{
     error::{Api, Db},
-    opt::{
-        auth::{Record, Root},
-        IntoEndpoint, IntoQuery,
-    },
+    opt::{auth::Record, IntoEndpoint, IntoQuery},
     sql::Thing,
     Connection, Error, Surreal,
 };
 
+mod assets;
+mod auth;
+mod migrations;
+mod pool;
+mod publish;
+mod query;
+mod revisions;
+
+pub use assets::{
+    Asset, AssetBackend, AssetError, AssetLocation, AssetRef, S3Backend, S3Client,
+    SurrealBlobBackend,
+};
+pub use auth::{Action, Permission, DEFAULT_PERMISSIONS, READ, WRITE};
+pub use migrations::{Migration, MigrationError};
+use pool::{PoolHandle, SurrealPool};
+pub use query::{Page, QueryOptions, SortDirection};
+pub use revisions::Revision;
+
+/// Default number of connections each of a [`SurrealStore`]'s pools will
+/// open before `init`/`init_system` start waiting for one to free up.
+const DEFAULT_POOL_SIZE: usize = 10;
+
 fn thing_to_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -28,6 +47,9 @@ pub struct SurrealConnection<C: Connection> {
     namespace: String,
     db: String,
     inner: Surreal<C>,
+    /// Set when this handle was checked out of a [`SurrealPool`]; its `Drop`
+    /// impl uses it to return the connection instead of discarding it.
+    retire: Option<Arc<PoolHandle<C>>>,
 }
 
 impl<C: Connection> Deref for SurrealConnection<C> {
@@ -73,11 +95,8 @@ impl<D> From<Item<D>> for SurrealItem<D> {
 }
 
 pub struct SurrealStore<C: Connection, S, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync> {
-    endpoint: P,
-    namespace: String,
-    db: String,
-    connection_marker: PhantomData<C>,
-    scheme_marker: PhantomData<S>,
+    pool: Arc<SurrealPool<C, S, P>>,
+    system_pool: Arc<SurrealPool<C, S, P>>,
 }
 
 impl<C: Connection, S, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync> Clone
@@ -85,23 +104,31 @@ impl<C: Connection, S, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync> Clo
 {
     fn clone(&self) -> Self {
         Self {
-            endpoint: self.endpoint.clone(),
-            namespace: self.namespace.clone(),
-            db: self.db.clone(),
-            connection_marker: PhantomData,
-            scheme_marker: PhantomData,
+            pool: Arc::clone(&self.pool),
+            system_pool: Arc::clone(&self.system_pool),
         }
     }
 }
 
-impl<C: Connection, S, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync> SurrealStore<C, S, P> {
+impl<C: Connection, S: Send + Sync, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync>
+    SurrealStore<C, S, P>
+{
     pub fn new(address: P, namespace: String, db: String) -> Self {
+        Self::with_pool_size(address, namespace, db, DEFAULT_POOL_SIZE)
+    }
+
+    /// Like [`Self::new`], but with an explicit upper bound on how many
+    /// connections each of the plain and system pools may open at once.
+    pub fn with_pool_size(address: P, namespace: String, db: String, max_size: usize) -> Self {
         Self {
-            endpoint: address,
-            namespace,
-            db,
-            connection_marker: PhantomData,
-            scheme_marker: PhantomData,
+            pool: SurrealPool::new(
+                address.clone(),
+                namespace.clone(),
+                db.clone(),
+                false,
+                max_size,
+            ),
+            system_pool: SurrealPool::new(address, namespace, db, true, max_size),
         }
     }
 }
@@ -117,42 +144,28 @@ impl<
     type Connection = SurrealConnection<C>;
 
     async fn init(&self) -> Result<Self::Connection, Self::Error> {
-        let inner = Surreal::new(self.endpoint.to_owned()).await?;
-
-        inner.use_ns(&self.namespace).await?;
-        inner.use_db(&self.db).await?;
-
-        Ok(SurrealConnection {
-            namespace: self.namespace.clone(),
-            db: self.namespace.clone(),
-            inner,
-        })
+        self.pool.get().await
     }
 
     async fn init_system(&self) -> Result<Self::Connection, Self::Error> {
-        let inner = Surreal::new(self.endpoint.to_owned()).await?;
-
-        inner.use_ns(&self.namespace).await?;
-        inner.use_db(&self.db).await?;
-
-        inner
-            .signin(Root {
-                username: "root",
-                password: "root",
-            })
-            .await?;
-
-        Ok(SurrealConnection {
-            namespace: self.namespace.clone(),
-            db: self.namespace.clone(),
-            inner,
-        })
+        self.system_pool.get().await
     }
 }
 
 impl<C: Connection> Drop for SurrealConnection<C> {
     fn drop(&mut self) {
-        println!("MEMORY LEAK PREVENTED!!!!");
+        if let Some(handle) = self.retire.take() {
+            // Only the last surviving clone of this checkout actually hands
+            // the connection back; the others just drop their `Arc`.
+            if Arc::strong_count(&handle) == 1 {
+                handle.release(SurrealConnection {
+                    namespace: self.namespace.clone(),
+                    db: self.db.clone(),
+                    inner: self.inner.clone(),
+                    retire: None,
+                });
+            }
+        }
     }
 }
 
@@ -176,16 +189,21 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
             .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
             .query("UPSERT $draft_id SET inner = $inner")
             .query("UPSERT type::thing(string::concat($doc, '_meta'), $id) SET draft = $draft_id, modified_at = time::now()")
-            .query(
+            .query("LET $next_version = (SELECT VALUE version FROM type::table(string::concat($doc, '_revision')) WHERE meta = $meta_id ORDER BY version DESC LIMIT 1)[0] ?? 0")
+            .query("CREATE type::table(string::concat($doc, '_revision')) SET meta = $meta_id, version = $next_version + 1, inner = $inner, author = $auth.id")
+            .query(format!(
                 "SELECT
                 id,
                 created_at,
                 modified_at,
-                IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
+                IF draft IS NOT NONE THEN draft.inner
+                ELSE IF published.published_at IS NOT NONE AND published.published_at > time::now() AND $auth.id IS NONE THEN NONE
+                ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM $meta_id
-            FETCH draft, published",
-            )
+            {}",
+                crate::assets::fetch_clause::<D>(&["draft", "published"], &["draft.inner", "published.inner"]).unwrap_or_default()
+            ))
             .bind(Bindings {
                 doc: D::identifier().into(),
                 id: id.to_owned().into(),
@@ -194,7 +212,7 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
             .await?;
 
         let thingy: Option<SurrealItem<serde_json::Value>> =
-            result.take(4).expect("this should always succeed");
+            result.take(6).expect("this should always succeed");
 
         Ok(thingy
             .expect("this option should always return something")
@@ -228,11 +246,27 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
         &self,
         item: Item<D>,
     ) -> Result<Item<D>, Self::Error> {
-        let updated_thingy: Option<SurrealItem<D>> = self
-            .upsert((D::identifier(), item.id.to_owned()))
-            .content(SurrealItem::<D>::from(item))
+        #[derive(Serialize)]
+        struct Bindings<D> {
+            doc: String,
+            id: String,
+            content: SurrealItem<D>,
+        }
+
+        let mut result = self
+            .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query("LET $next_version = (SELECT VALUE version FROM type::table(string::concat($doc, '_revision')) WHERE meta = $meta_id ORDER BY version DESC LIMIT 1)[0] ?? 0")
+            .query("UPSERT type::thing($doc, $id) CONTENT $content")
+            .query("CREATE type::table(string::concat($doc, '_revision')) SET meta = $meta_id, version = $next_version + 1, inner = $content.inner, author = $auth.id")
+            .bind(Bindings {
+                doc: D::identifier().to_owned(),
+                id: item.id.to_owned(),
+                content: SurrealItem::<D>::from(item),
+            })
             .await?;
 
+        let updated_thingy: Option<SurrealItem<D>> = result.take(2)?;
+
         Ok(updated_thingy
             .expect("surreal should return data regardless")
             .into())
@@ -242,25 +276,13 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
         todo!()
     }
 
+    /// Returns the first page of `D`'s items, using [`Self::query`]'s
+    /// default [`QueryOptions`]. Callers that need pagination, filtering,
+    /// or full-text search should call [`Self::query`] directly.
     async fn get_all<D: Document + DeserializeOwned + Send>(
         &self,
     ) -> Result<Vec<Item<serde_json::Value>>, Self::Error> {
-        let result = self
-            .query(
-                "SELECT
-                id,
-                created_at,
-                modified_at,
-                IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
-                published.published_at AS published_at
-            FROM type::table(string::concat($doc, '_meta'))
-            FETCH draft, published",
-            )
-            .bind(("doc", D::identifier()))
-            .await?
-            .take::<Vec<SurrealItem<serde_json::Value>>>(0)?;
-
-        Ok(result.into_iter().map(Into::into).collect())
+        Ok(self.query::<D>(QueryOptions::default()).await?.items)
     }
 
     async fn get_by_id<D: Document + DeserializeOwned + Send>(
@@ -275,16 +297,20 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
 
         Ok(self
             .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
-            .query(
+            .query(format!(
                 "SELECT
                 id,
                 created_at,
                 modified_at,
-                IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
+                IF draft IS NOT NONE THEN draft.inner
+                ELSE IF published.published_at IS NOT NONE AND published.published_at > time::now() AND $auth.id IS NONE THEN NONE
+                ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM $meta_id
-            FETCH draft, published",
-            )
+            WHERE draft IS NOT NONE OR published.published_at IS NONE OR published.published_at <= time::now() OR $auth.id IS NOT NONE
+            {}",
+                crate::assets::fetch_clause::<D>(&["draft", "published"], &["draft.inner", "published.inner"]).unwrap_or_default()
+            ))
             .bind(Bindings {
                 doc: D::identifier().into(),
                 id: id.to_owned().into(),
@@ -327,52 +353,12 @@ impl<C: Connection> scalar::DatabaseConnection for SurrealConnection<C> {
     }
 }
 
-impl<C: Connection> SurrealConnection<C> {
-    pub async fn init_doc<D: Document>(&self) {
-        let published_table = D::identifier();
-        let draft_table = format!("{published_table}_draft");
-        let meta_table = format!("{published_table}_meta");
-        self
-            // published documents
-            .query(format!("DEFINE TABLE OVERWRITE {published_table} SCHEMAFULL PERMISSIONS FOR select WHERE true FOR create, update, delete WHERE $auth.id IS NOT NONE"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS published_at ON {published_table} TYPE option<datetime>"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS inner ON {published_table} FLEXIBLE TYPE object"))
-            // drafts
-            .query(format!("DEFINE TABLE OVERWRITE {draft_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS inner ON {draft_table} FLEXIBLE TYPE object"))
-            // meta table
-            .query(format!("DEFINE TABLE OVERWRITE {meta_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS created_at ON {meta_table} TYPE datetime DEFAULT time::now()"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS modified_at ON {meta_table} TYPE datetime"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS draft ON {meta_table} TYPE option<record<{draft_table}>>"))
-            .query(format!("DEFINE FIELD IF NOT EXISTS published ON {meta_table} TYPE option<record<{published_table}>>"))
-            .await
-            .expect(&format!("setting up tables for {published_table} failed"));
-    }
-
-    pub async fn init_auth(&self) {
-        self
-            .query("DEFINE TABLE OVERWRITE sc__editor SCHEMAFULL PERMISSIONS FOR select, update, delete WHERE id = $auth.id OR $auth.admin = true FOR create WHERE $auth.admin = true")
-            .query("DEFINE FIELD IF NOT EXISTS name ON sc__editor TYPE string")
-            .query("DEFINE FIELD IF NOT EXISTS email ON sc__editor TYPE string ASSERT string::is::email($value)")
-            .query("DEFINE FIELD IF NOT EXISTS password ON sc__editor TYPE string")
-            .query("DEFINE FIELD IF NOT EXISTS admin ON sc__editor TYPE bool")
-            .query("DEFINE INDEX email ON user FIELDS email UNIQUE")
-            .query("
-            DEFINE ACCESS OVERWRITE sc__editor ON DATABASE TYPE RECORD
-            SIGNIN (
-                SELECT * FROM sc__editor WHERE email = $email AND crypto::argon2::compare(password, $password)
-            )
-        ").await.expect("auth setup failed");
-    }
-}
-
 // TODO: unit tests
 
 #[macro_export]
 macro_rules! doc_init {
     ($db:ident, $doc:ty) => {
-        $db.init_doc::<$doc>().await;
+        $db.init_doc::<$doc>().await?;
     };
     ($db:ident, $doc:ty, $($docs:ty),+) => {
         ::scalar_surreal::doc_init!($db, $doc);
@@ -383,7 +369,7 @@ macro_rules! doc_init {
 #[macro_export]
 macro_rules! init {
     ($db:ident, $($docs:ty),+) => {
-        $db.init_auth().await;
+        $db.init_auth().await?;
         ::scalar_surreal::doc_init!($db, $($docs),+);
     };
-}
\ No newline at end of file
+}
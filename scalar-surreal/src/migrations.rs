@@ -0,0 +1,297 @@
+use scalar::{DateTime, Document, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::SurrealConnection;
+
+/// A single, named step of SurrealQL run by [`SurrealConnection::migrate`].
+///
+/// `name` must stay stable across releases: it's the key `migrate` uses to
+/// recognize a migration it has already applied.
+pub struct Migration {
+    pub name: String,
+    pub body: String,
+}
+
+impl Migration {
+    pub fn new(name: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            body: body.into(),
+        }
+    }
+
+    /// A content hash of `body`, used to detect a migration that was edited
+    /// after it was already applied. Not cryptographic, just stable.
+    fn checksum(&self) -> String {
+        format!("{:016x}", fnv1a64(self.body.as_bytes()))
+    }
+}
+
+/// A stable 64-bit FNV-1a hash. Unlike `std`'s `DefaultHasher`, this is
+/// guaranteed to produce the same output across Rust releases, which
+/// matters anywhere the hash itself is persisted (migration checksums and
+/// names) rather than just used within one process's lifetime.
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
+#[derive(Serialize, Deserialize)]
+struct AppliedMigration {
+    name: String,
+    checksum: String,
+    #[allow(dead_code)]
+    applied_at: DateTime<Utc>,
+}
+
+/// An error from [`SurrealConnection::migrate`].
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(surrealdb::Error),
+    /// A migration that was already recorded in `sc__migrations` no longer
+    /// matches its stored checksum, meaning its `body` changed after it ran.
+    ChecksumMismatch {
+        name: String,
+    },
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "{e}"),
+            Self::ChecksumMismatch { name } => write!(
+                f,
+                "migration `{name}` was already applied with a different checksum; refusing to re-run it"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(e) => Some(e),
+            Self::ChecksumMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<surrealdb::Error> for MigrationError {
+    fn from(value: surrealdb::Error) -> Self {
+        Self::Database(value)
+    }
+}
+
+/// Maps a field's entry in [`Document::fields`] to the SurrealQL type used
+/// for the `DEFINE FIELD` generated for it. Anything we don't recognize
+/// falls back to `option<any>` rather than guessing wrong.
+fn surql_type_for(field: &serde_json::Value) -> &'static str {
+    match field.get("type").and_then(|t| t.as_str()) {
+        Some("String" | "string") => "option<string>",
+        Some("I32" | "I64" | "Int" | "int") => "option<int>",
+        Some("F32" | "F64" | "Float" | "float") => "option<float>",
+        Some("Bool" | "bool") => "option<bool>",
+        _ => "option<any>",
+    }
+}
+
+/// Derives the forward migration that brings a `D`'s published table's
+/// fields up to date with `D::fields()`, without overwriting the table or
+/// any field that's no longer declared.
+pub fn migration_for_fields<D: Document>() -> Migration {
+    let table = D::identifier();
+    let schema = serde_json::to_value(D::fields()).unwrap_or(serde_json::Value::Null);
+
+    let mut body = format!(
+        "DEFINE TABLE IF NOT EXISTS {table} SCHEMAFULL PERMISSIONS FOR select WHERE true FOR create, update, delete WHERE $auth.id IS NOT NONE;\n"
+    );
+
+    if let serde_json::Value::Array(fields) = schema {
+        for field in fields {
+            let Some(name) = field.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+
+            let ty = surql_type_for(&field);
+            body += &format!("DEFINE FIELD IF NOT EXISTS inner.{name} ON {table} TYPE {ty};\n");
+        }
+    }
+
+    // The name carries a hash of the body, so a changed field list is a new
+    // migration (whose `IF NOT EXISTS` statements skip what's already
+    // defined) rather than a checksum mismatch on an old one.
+    let name = format!("{table}::fields::{:016x}", fnv1a64(body.as_bytes()));
+    Migration::new(name, body)
+}
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Applies `migrations` that haven't run yet, in order, each inside its
+    /// own transaction, recording them in `sc__migrations` as it goes.
+    ///
+    /// Refuses to run (and leaves the database untouched) if a migration
+    /// that already ran no longer matches its recorded checksum: every
+    /// already-applied migration in `migrations` is checked up front, before
+    /// any new one is run, so a mismatch discovered partway through the list
+    /// can't follow migrations that already committed earlier in this call.
+    pub async fn migrate(&self, migrations: &[Migration]) -> Result<(), MigrationError> {
+        self.query("DEFINE TABLE IF NOT EXISTS sc__migrations SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.admin = true")
+            .query("DEFINE FIELD IF NOT EXISTS name ON sc__migrations TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS checksum ON sc__migrations TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS applied_at ON sc__migrations TYPE datetime DEFAULT time::now()")
+            .await?;
+
+        let applied: Vec<AppliedMigration> = self
+            .query("SELECT name, checksum, applied_at FROM sc__migrations")
+            .await?
+            .take(0)?;
+
+        for migration in migrations {
+            if let Some(existing) = applied.iter().find(|a| a.name == migration.name) {
+                if existing.checksum != migration.checksum() {
+                    return Err(MigrationError::ChecksumMismatch {
+                        name: migration.name.clone(),
+                    });
+                }
+            }
+        }
+
+        for migration in migrations {
+            if applied.iter().any(|a| a.name == migration.name) {
+                continue;
+            }
+
+            self.query("BEGIN TRANSACTION")
+                .query(migration.body.clone())
+                .query("CREATE sc__migrations SET name = $migration_name, checksum = $migration_checksum, applied_at = time::now()")
+                .query("COMMIT TRANSACTION")
+                .bind(("migration_name", migration.name.clone()))
+                .bind(("migration_checksum", migration.checksum()))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn init_doc<D: Document>(&self) -> Result<(), MigrationError> {
+        let published_table = D::identifier();
+        let draft_table = format!("{published_table}_draft");
+        let meta_table = format!("{published_table}_meta");
+        let revision_table = format!("{published_table}_revision");
+
+        let mut migrations = vec![
+            migration_for_fields::<D>(),
+            Migration::new(
+                format!("{published_table}::scaffold"),
+                format!(
+                    "DEFINE FIELD IF NOT EXISTS published_at ON {published_table} TYPE option<datetime>;
+DEFINE FIELD IF NOT EXISTS inner ON {published_table} FLEXIBLE TYPE object;
+DEFINE TABLE IF NOT EXISTS {draft_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE;
+DEFINE FIELD IF NOT EXISTS inner ON {draft_table} FLEXIBLE TYPE object;
+DEFINE TABLE IF NOT EXISTS {meta_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE;
+DEFINE FIELD IF NOT EXISTS created_at ON {meta_table} TYPE datetime DEFAULT time::now();
+DEFINE FIELD IF NOT EXISTS modified_at ON {meta_table} TYPE datetime;
+DEFINE FIELD IF NOT EXISTS draft ON {meta_table} TYPE option<record<{draft_table}>>;
+DEFINE FIELD IF NOT EXISTS published ON {meta_table} TYPE option<record<{published_table}>>;"
+                ),
+            ),
+            Migration::new(
+                format!("{published_table}::scheduling"),
+                format!(
+                    "DEFINE FIELD IF NOT EXISTS expires_at ON {meta_table} TYPE option<datetime>;"
+                ),
+            ),
+            Migration::new(
+                format!("{published_table}::revisions"),
+                format!(
+                    "DEFINE TABLE IF NOT EXISTS {revision_table} SCHEMAFULL PERMISSIONS FOR select, create WHERE $auth.id IS NOT NONE;
+DEFINE FIELD IF NOT EXISTS meta ON {revision_table} TYPE record<{meta_table}>;
+DEFINE FIELD IF NOT EXISTS version ON {revision_table} TYPE int;
+DEFINE FIELD IF NOT EXISTS inner ON {revision_table} FLEXIBLE TYPE object;
+DEFINE FIELD IF NOT EXISTS author ON {revision_table} TYPE option<record<sc__editor>>;
+DEFINE FIELD IF NOT EXISTS created_at ON {revision_table} TYPE datetime DEFAULT time::now();"
+                ),
+            ),
+            Migration::new(
+                format!("{published_table}::roles"),
+                format!(
+                    // Plain `DEFINE TABLE`, not `OVERWRITE`: this only needs
+                    // to change the `PERMISSIONS` clause, and (like `DEFINE
+                    // TABLE IF NOT EXISTS` elsewhere in this file) redefining
+                    // a table in place doesn't touch the `DEFINE FIELD`/
+                    // `DEFINE INDEX` entries already recorded against it.
+                    // `OVERWRITE` is for forcing a destructive recreate,
+                    // which is exactly what this migration subsystem exists
+                    // to avoid.
+                    "DEFINE TABLE {published_table} SCHEMAFULL PERMISSIONS FOR select WHERE true {write};
+DEFINE TABLE {draft_table} SCHEMAFULL PERMISSIONS {read_write};
+DEFINE TABLE {meta_table} SCHEMAFULL PERMISSIONS {meta_read_write};",
+                    write = crate::auth::permissions_clause(published_table, &[crate::auth::WRITE]),
+                    read_write = crate::auth::permissions_clause(published_table, crate::auth::DEFAULT_PERMISSIONS),
+                    meta_read_write = crate::auth::meta_permissions_clause(published_table),
+                ),
+            ),
+        ];
+
+        let searchable = crate::query::searchable_fields::<D>();
+        if !searchable.is_empty() {
+            let analyzer = format!("{published_table}_search");
+            let mut body =
+                format!("DEFINE ANALYZER IF NOT EXISTS {analyzer} TOKENIZERS blank, class FILTERS lowercase, ascii;\n");
+
+            for field in &searchable {
+                body += &format!(
+                    "DEFINE INDEX IF NOT EXISTS {published_table}_{field}_search ON {published_table} FIELDS inner.{field} SEARCH ANALYZER {analyzer} BM25;\n"
+                );
+            }
+
+            migrations.push(Migration::new(format!("{published_table}::search"), body));
+        }
+
+        self.migrate(&migrations).await
+    }
+
+    pub async fn init_auth(&self) -> Result<(), MigrationError> {
+        let migrations = [
+            Migration::new(
+                "sc__editor::scaffold",
+                "DEFINE TABLE IF NOT EXISTS sc__editor SCHEMAFULL PERMISSIONS FOR select, update, delete WHERE id = $auth.id OR $auth.admin = true FOR create WHERE $auth.admin = true;
+DEFINE FIELD IF NOT EXISTS name ON sc__editor TYPE string;
+DEFINE FIELD IF NOT EXISTS email ON sc__editor TYPE string ASSERT string::is::email($value);
+DEFINE FIELD IF NOT EXISTS password ON sc__editor TYPE string;
+DEFINE FIELD IF NOT EXISTS admin ON sc__editor TYPE bool;
+DEFINE INDEX IF NOT EXISTS email ON sc__editor FIELDS email UNIQUE;
+DEFINE ACCESS IF NOT EXISTS sc__editor ON DATABASE TYPE RECORD
+SIGNIN (
+    SELECT * FROM sc__editor WHERE email = $email AND crypto::argon2::compare(password, $password)
+);",
+            ),
+            Migration::new(
+                "sc__editor::roles",
+                "DEFINE FIELD IF NOT EXISTS roles ON sc__editor TYPE set<string> DEFAULT [];",
+            ),
+            Migration::new(
+                "sc__asset::scaffold",
+                "DEFINE TABLE IF NOT EXISTS sc__asset SCHEMAFULL PERMISSIONS FOR select WHERE true FOR create, update, delete WHERE $auth.id IS NOT NONE;
+DEFINE FIELD IF NOT EXISTS filename ON sc__asset TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS content_type ON sc__asset TYPE string;
+DEFINE FIELD IF NOT EXISTS size ON sc__asset TYPE int;
+DEFINE FIELD IF NOT EXISTS checksum ON sc__asset TYPE string;
+DEFINE FIELD IF NOT EXISTS created_at ON sc__asset TYPE datetime DEFAULT time::now();
+DEFINE FIELD IF NOT EXISTS uploaded_by ON sc__asset TYPE option<record<sc__editor>>;
+DEFINE INDEX IF NOT EXISTS checksum ON sc__asset FIELDS checksum;
+DEFINE TABLE IF NOT EXISTS sc__asset_blob SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE;
+DEFINE FIELD IF NOT EXISTS bytes ON sc__asset_blob TYPE bytes;",
+            ),
+        ];
+
+        self.migrate(&migrations).await
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex};
+
+use surrealdb::{opt::auth::Root, opt::IntoEndpoint, Connection, Surreal};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::SurrealConnection;
+
+/// Handed out to a checked-out [`SurrealConnection`] so its `Drop` impl can
+/// return the underlying handle to the pool instead of letting it go.
+///
+/// `SurrealConnection` is `Clone`, so several handles may share one
+/// `PoolHandle` via `Arc`; only the last clone to drop actually releases the
+/// connection back to the pool.
+pub(crate) struct PoolHandle<C: Connection> {
+    idle_tx: mpsc::UnboundedSender<SurrealConnection<C>>,
+    semaphore: Arc<Semaphore>,
+}
+
+/// Builds and health-checks the raw connections a [`SurrealPool`] manages.
+///
+/// Modeled on deadpool's manager/pool split: the manager knows how to create
+/// and recycle a single connection, the pool owns the idle queue and the
+/// concurrency limit around it.
+struct ConnectionManager<C, S, P> {
+    endpoint: P,
+    namespace: String,
+    db: String,
+    system: bool,
+    marker: std::marker::PhantomData<(C, S)>,
+}
+
+impl<C, S, P> ConnectionManager<C, S, P>
+where
+    C: Connection,
+    S: Send + Sync,
+    P: IntoEndpoint<S, Client = C> + Clone + Send + Sync,
+{
+    async fn create(&self) -> Result<SurrealConnection<C>, surrealdb::Error> {
+        let inner = Surreal::new(self.endpoint.to_owned()).await?;
+
+        inner.use_ns(&self.namespace).await?;
+        inner.use_db(&self.db).await?;
+
+        if self.system {
+            inner
+                .signin(Root {
+                    username: "root",
+                    password: "root",
+                })
+                .await?;
+        }
+
+        Ok(SurrealConnection {
+            namespace: self.namespace.clone(),
+            db: self.db.clone(),
+            inner,
+            retire: None,
+        })
+    }
+
+    /// Pings the server and reports whether `conn` is still alive.
+    ///
+    /// `RETURN 1`, not `SELECT 1`: SurrealQL's `SELECT` requires a `FROM`.
+    async fn recycle(&self, conn: &SurrealConnection<C>) -> Result<(), surrealdb::Error> {
+        conn.query("RETURN 1").await?;
+        Ok(())
+    }
+}
+
+/// A bounded pool of [`SurrealConnection`]s.
+///
+/// Every [`SurrealStore`](crate::SurrealStore) keeps one of these for plain
+/// connections and one for system (root) connections, rather than opening a
+/// fresh socket on every `init`/`init_system` call.
+pub struct SurrealPool<C: Connection, S, P: IntoEndpoint<S, Client = C> + Clone + Send + Sync> {
+    manager: ConnectionManager<C, S, P>,
+    semaphore: Arc<Semaphore>,
+    idle_tx: mpsc::UnboundedSender<SurrealConnection<C>>,
+    idle_rx: Mutex<mpsc::UnboundedReceiver<SurrealConnection<C>>>,
+}
+
+impl<C, S, P> SurrealPool<C, S, P>
+where
+    C: Connection,
+    S: Send + Sync,
+    P: IntoEndpoint<S, Client = C> + Clone + Send + Sync,
+{
+    pub(crate) fn new(
+        endpoint: P,
+        namespace: String,
+        db: String,
+        system: bool,
+        max_size: usize,
+    ) -> Arc<Self> {
+        let (idle_tx, idle_rx) = mpsc::unbounded_channel();
+
+        Arc::new(Self {
+            manager: ConnectionManager {
+                endpoint,
+                namespace,
+                db,
+                system,
+                marker: std::marker::PhantomData,
+            },
+            semaphore: Arc::new(Semaphore::new(max_size)),
+            idle_tx,
+            idle_rx: Mutex::new(idle_rx),
+        })
+    }
+
+    /// Checks out a connection, reusing an idle one if a live handle is
+    /// available, falling back to creating a brand new one otherwise.
+    pub(crate) async fn get(self: &Arc<Self>) -> Result<SurrealConnection<C>, surrealdb::Error> {
+        let permit = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("pool semaphore is never closed");
+
+        let mut conn = loop {
+            let candidate = self
+                .idle_rx
+                .lock()
+                .expect("pool idle queue mutex poisoned")
+                .try_recv()
+                .ok();
+
+            match candidate {
+                Some(conn) if self.manager.recycle(&conn).await.is_ok() => break conn,
+                Some(_dead) => continue,
+                None => break self.manager.create().await?,
+            }
+        };
+
+        // Only forgotten once a connection is actually secured, so that a
+        // `create` failure above just drops `permit` normally and returns
+        // its slot to the semaphore instead of leaking it. Once forgotten,
+        // it's released manually (via `PoolHandle`) when the checked-out
+        // connection is fully dropped, not when this function returns.
+        permit.forget();
+
+        conn.retire = Some(Arc::new(PoolHandle {
+            idle_tx: self.idle_tx.clone(),
+            semaphore: Arc::clone(&self.semaphore),
+        }));
+
+        Ok(conn)
+    }
+}
+
+impl<C: Connection> PoolHandle<C> {
+    /// Returns `conn` to the idle queue and frees its checkout permit.
+    pub(crate) fn release(&self, conn: SurrealConnection<C>) {
+        let _ = self.idle_tx.send(conn);
+        self.semaphore.add_permits(1);
+    }
+}
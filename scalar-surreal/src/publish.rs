@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+
+use scalar::{DateTime, Document, Item, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use surrealdb::Connection;
+
+use crate::{SurrealConnection, SurrealItem};
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Promotes the current draft of `id` into the published `{doc}` record,
+    /// clearing the draft and stamping `published.published_at`.
+    ///
+    /// `at` lets a caller schedule a future go-live: the record is written
+    /// now, but [`Self::get_all`](scalar::DatabaseConnection::get_all) and
+    /// [`Self::get_by_id`](scalar::DatabaseConnection::get_by_id) hide it
+    /// from unauthenticated callers until `published_at` has passed.
+    pub async fn publish<D: Document + Send>(
+        &self,
+        id: &str,
+        at: Option<DateTime<Utc>>,
+    ) -> Result<Item<serde_json::Value>, surrealdb::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+            published_at: DateTime<Utc>,
+        }
+
+        let mut result = self
+            .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
+            .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query("LET $published_id = type::thing($doc, $id)")
+            .query("LET $draft_inner = (SELECT VALUE inner FROM $draft_id)[0]")
+            .query("UPSERT $published_id SET inner = $draft_inner, published_at = $published_at")
+            .query("UPDATE $meta_id SET published = $published_id, draft = NONE, modified_at = time::now()")
+            .query("DELETE $draft_id")
+            .query(
+                "SELECT
+                id,
+                created_at,
+                modified_at,
+                published.inner AS inner,
+                published.published_at AS published_at
+            FROM $meta_id
+            FETCH published",
+            )
+            .bind(Bindings {
+                doc: D::identifier().into(),
+                id: id.to_owned().into(),
+                published_at: at.unwrap_or_else(Utc::now),
+            })
+            .await?;
+
+        let item: Option<SurrealItem<serde_json::Value>> = result.take(7)?;
+
+        Ok(item
+            .expect("publishing should always return the promoted item")
+            .into())
+    }
+
+    /// Sets (or, with `None`, clears) `id`'s automatic-unpublish time.
+    pub async fn set_expiry<D: Document + Send>(
+        &self,
+        id: &str,
+        at: Option<DateTime<Utc>>,
+    ) -> Result<(), surrealdb::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+            expires_at: Option<DateTime<Utc>>,
+        }
+
+        self.query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query("UPDATE $meta_id SET expires_at = $expires_at")
+            .bind(Bindings {
+                doc: D::identifier().into(),
+                id: id.to_owned().into(),
+                expires_at: at,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sweeps `D`'s meta table in one query: rows whose `published_at` has
+    /// passed are reported as now-live, and rows whose `expires_at` has
+    /// passed are retracted (their `published` record unlinked). Returns
+    /// every affected [`Item`] so a caller can run this from a scheduler.
+    pub async fn publish_due<D: Document + DeserializeOwned + Send>(
+        &self,
+    ) -> Result<Vec<Item<serde_json::Value>>, surrealdb::Error> {
+        let mut result = self
+            .query("LET $meta_table = type::table(string::concat($doc, '_meta'))")
+            .query(
+                "LET $due_to_publish = (
+                SELECT id, created_at, modified_at, published.inner AS inner, published.published_at AS published_at
+                FROM $meta_table
+                WHERE published.published_at IS NOT NONE AND published.published_at <= time::now()
+                FETCH published
+            )",
+            )
+            .query(
+                "LET $due_to_expire = (
+                SELECT id, created_at, modified_at, published.inner AS inner, published.published_at AS published_at
+                FROM $meta_table
+                WHERE expires_at IS NOT NONE AND expires_at <= time::now() AND published IS NOT NONE
+                FETCH published
+            )",
+            )
+            .query("UPDATE $meta_table SET published = NONE, expires_at = NONE WHERE expires_at IS NOT NONE AND expires_at <= time::now() AND published IS NOT NONE")
+            .query("RETURN array::union($due_to_publish, $due_to_expire)")
+            .bind(("doc", D::identifier()))
+            .await?;
+
+        let items: Vec<SurrealItem<serde_json::Value>> = result.take(4)?;
+
+        Ok(items.into_iter().map(Into::into).collect())
+    }
+}
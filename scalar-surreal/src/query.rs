@@ -0,0 +1,306 @@
+use scalar::{Document, Item};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{SurrealConnection, SurrealItem};
+
+/// How many rows [`SurrealConnection::query`] asks for when the caller
+/// doesn't specify a `limit` (as with [`QueryOptions::default`]), so it never
+/// silently scans an entire table.
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    fn as_surql(self) -> &'static str {
+        match self {
+            Self::Ascending => "ASC",
+            Self::Descending => "DESC",
+        }
+    }
+}
+
+/// Options for [`SurrealConnection::query`]: bounds, ordering, and
+/// optional filtering/full-text search over a `Document`'s items (its meta
+/// table, or its published table when `search` is set).
+pub struct QueryOptions {
+    pub limit: Option<u32>,
+    pub start: Option<u32>,
+    pub sort: Option<(String, SortDirection)>,
+    /// A full-text search term, matched against the fields a `Document`
+    /// marks as searchable.
+    pub search: Option<String>,
+    /// Equality filters over fields of the document's `inner` value.
+    pub filters: Vec<(String, serde_json::Value)>,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            start: None,
+            sort: None,
+            search: None,
+            filters: Vec::new(),
+        }
+    }
+}
+
+/// A page of results from [`SurrealConnection::query`]: the items
+/// themselves, the total number of rows matching the query, and a cursor
+/// for the next page, if there is one.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub next_cursor: Option<u32>,
+}
+
+/// The names of the fields a `Document` marks searchable in its
+/// [`Document::fields`] schema, i.e. those with `"searchable": true`.
+pub(crate) fn searchable_fields<D: Document>() -> Vec<String> {
+    let schema = serde_json::to_value(D::fields()).unwrap_or(serde_json::Value::Null);
+    let mut fields = Vec::new();
+
+    if let serde_json::Value::Array(entries) = schema {
+        for entry in entries {
+            let searchable = entry
+                .get("searchable")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            if let Some(name) = searchable
+                .then(|| entry.get("name"))
+                .flatten()
+                .and_then(|v| v.as_str())
+            {
+                fields.push(name.to_owned());
+            }
+        }
+    }
+
+    fields
+}
+
+/// Every field name `D` declares in [`Document::fields`]. Used to validate
+/// caller-supplied filter/sort field names before they're interpolated into
+/// SurrealQL: values are always bound, but field *names* can't be, so they
+/// go through this allowlist instead.
+fn known_fields<D: Document>() -> Vec<String> {
+    let schema = serde_json::to_value(D::fields()).unwrap_or(serde_json::Value::Null);
+    let mut fields = Vec::new();
+
+    if let serde_json::Value::Array(entries) = schema {
+        for entry in entries {
+            if let Some(name) = entry.get("name").and_then(|v| v.as_str()) {
+                fields.push(name.to_owned());
+            }
+        }
+    }
+
+    fields
+}
+
+/// Builds bound equality conditions for `filters`, dropping any field not
+/// in `known` instead of interpolating an unrecognized name into SurrealQL.
+/// `predicate` renders one field/parameter-name pair into the condition for
+/// whichever table is being queried.
+fn filter_conditions(
+    filters: &[(String, serde_json::Value)],
+    known: &[String],
+    predicate: impl Fn(&str, &str) -> String,
+) -> (Vec<String>, Vec<(String, serde_json::Value)>) {
+    let mut conditions = Vec::new();
+    let mut bindings = Vec::new();
+
+    for (field, value) in filters {
+        if !known.iter().any(|f| f == field) {
+            continue;
+        }
+
+        let param = format!("filter_{}", bindings.len());
+        conditions.push(predicate(field, &param));
+        bindings.push((param, value.clone()));
+    }
+
+    (conditions, bindings)
+}
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Runs a bounded, optionally filtered and searched query over `D`,
+    /// instead of the unbounded scan [`Self::get_all`] used to do.
+    ///
+    /// Without a `search` term, this reads `D`'s meta table, same as
+    /// `get_all`/`get_by_id`. With one, it reads `D`'s published table
+    /// directly instead: that's the table `{doc}::search`'s `SEARCH
+    /// ANALYZER` index is actually defined on, and SurrealDB's `@@`/
+    /// `search::score` only consult an index on the table being queried —
+    /// so full-text search only covers published content, not drafts.
+    pub async fn query<D: Document + DeserializeOwned + Send>(
+        &self,
+        options: QueryOptions,
+    ) -> Result<Page<Item<serde_json::Value>>, surrealdb::Error> {
+        #[derive(Serialize)]
+        struct Bindings {
+            table: String,
+            meta_table: String,
+            limit: u32,
+            start: u32,
+            search: Option<String>,
+        }
+
+        let published_table = D::identifier().to_owned();
+        let meta_table = format!("{published_table}_meta");
+        let limit = options.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let start = options.start.unwrap_or(0);
+        let known = known_fields::<D>();
+
+        let (from_table, conditions, order_clause, filter_values, projection) = if options
+            .search
+            .is_some()
+        {
+            let predicate = searchable_fields::<D>()
+                .iter()
+                .map(|field| format!("inner.{field} @1@ $search"))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+
+            let mut conditions = vec![
+                "(published_at IS NONE OR published_at <= time::now() OR $auth.id IS NOT NONE)"
+                    .to_owned(),
+            ];
+            if !predicate.is_empty() {
+                conditions.push(format!("({predicate})"));
+            }
+
+            let (filter_conditions, filter_values) =
+                filter_conditions(&options.filters, &known, |field, param| {
+                    format!("inner.{field} = ${param}")
+                });
+            conditions.extend(filter_conditions);
+
+            let projection = "
+                id,
+                (SELECT VALUE created_at FROM type::table($meta_table) WHERE published = $parent.id LIMIT 1)[0] AS created_at,
+                (SELECT VALUE modified_at FROM type::table($meta_table) WHERE published = $parent.id LIMIT 1)[0] AS modified_at,
+                inner,
+                published_at"
+                    .to_owned();
+
+            (
+                published_table.clone(),
+                conditions,
+                "ORDER BY search::score(1) DESC".to_owned(),
+                filter_values,
+                projection,
+            )
+        } else {
+            let mut conditions = vec![
+                    "(draft IS NOT NONE OR published.published_at IS NONE OR published.published_at <= time::now() OR $auth.id IS NOT NONE)".to_owned(),
+                ];
+
+            let (filter_conditions, filter_values) =
+                filter_conditions(&options.filters, &known, |field, param| {
+                    format!(
+                        "(draft.inner.{field} = ${param} OR published.inner.{field} = ${param})"
+                    )
+                });
+            conditions.extend(filter_conditions);
+
+            let sort_field = options
+                .sort
+                .as_ref()
+                .filter(|(field, _)| known.iter().any(|f| f == field));
+
+            // Same `draft`/`published` split the filter predicate above
+            // qualifies its fields with: the meta table has no top-level
+            // `{field}`, only `draft.inner.{field}`/`published.inner.{field}`.
+            let order_clause = if let Some((field, direction)) = sort_field {
+                format!(
+                    "ORDER BY draft.inner.{field} ?? published.inner.{field} {}",
+                    direction.as_surql()
+                )
+            } else {
+                "ORDER BY created_at DESC".to_owned()
+            };
+
+            let projection = "
+                id,
+                created_at,
+                modified_at,
+                IF draft IS NOT NONE THEN draft.inner
+                ELSE IF published.published_at IS NOT NONE AND published.published_at > time::now() AND $auth.id IS NONE THEN NONE
+                ELSE published.inner END AS inner,
+                published.published_at AS published_at"
+                    .to_owned();
+
+            (
+                meta_table.clone(),
+                conditions,
+                order_clause,
+                filter_values,
+                projection,
+            )
+        };
+
+        let where_clause = conditions.join(" AND ");
+
+        let fetch_clause = if options.search.is_some() {
+            // The published table holds the content directly; nothing left
+            // to FETCH except embedded asset refs.
+            crate::assets::fetch_clause::<D>(&[], &["inner"])
+        } else {
+            crate::assets::fetch_clause::<D>(
+                &["draft", "published"],
+                &["draft.inner", "published.inner"],
+            )
+        }
+        .unwrap_or_default();
+
+        let mut query = self
+            .query(format!(
+                "SELECT count() FROM type::table($table) WHERE {where_clause} GROUP ALL"
+            ))
+            .query(format!(
+                "SELECT {projection} FROM type::table($table) WHERE {where_clause} {order_clause} LIMIT $limit START $start {fetch_clause}"
+            ))
+            .bind(Bindings {
+                table: from_table,
+                meta_table,
+                limit,
+                start,
+                search: options.search.clone(),
+            });
+
+        for (param, value) in filter_values {
+            query = query.bind((param, value));
+        }
+
+        let mut result = query.await?;
+
+        #[derive(Deserialize)]
+        struct Count {
+            count: u64,
+        }
+
+        let total = result
+            .take::<Vec<Count>>(0)?
+            .first()
+            .map(|c| c.count)
+            .unwrap_or(0);
+
+        let items: Vec<SurrealItem<serde_json::Value>> = result.take(1)?;
+        let returned = items.len() as u32;
+
+        let next_cursor = (start + returned < total as u32).then_some(start + returned);
+
+        Ok(Page {
+            items: items.into_iter().map(Into::into).collect(),
+            total,
+            next_cursor,
+        })
+    }
+}
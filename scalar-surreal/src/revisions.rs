@@ -0,0 +1,104 @@
+use std::borrow::Cow;
+
+use scalar::{DateTime, Document, Item, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use surrealdb::{sql::Thing, Connection};
+
+use crate::{SurrealConnection, SurrealItem};
+
+fn optional_thing_to_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let thing = Option::<Thing>::deserialize(deserializer)?;
+    Ok(thing.map(|t| t.id.to_raw()))
+}
+
+/// One snapshot of a document's `inner` at a point in time, recorded by
+/// [`SurrealConnection::draft`] and [`SurrealConnection::put`] every time
+/// they write.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Revision<D> {
+    pub version: i64,
+    pub inner: D,
+    #[serde(default, deserialize_with = "optional_thing_to_string")]
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<C: Connection> SurrealConnection<C> {
+    /// Lists `id`'s revisions, newest first.
+    pub async fn list_revisions<D: Document + DeserializeOwned + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<Revision<D>>, surrealdb::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+        }
+
+        self.query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query(
+                "SELECT version, inner, author, created_at
+                FROM type::table(string::concat($doc, '_revision'))
+                WHERE meta = $meta_id
+                ORDER BY version DESC",
+            )
+            .bind(Bindings {
+                doc: D::identifier().into(),
+                id: id.to_owned().into(),
+            })
+            .await?
+            .take(1)
+    }
+
+    /// Copies the stored `inner` of `version` back into `id`'s draft, then
+    /// appends a new revision for the restore itself so history stays
+    /// append-only.
+    pub async fn restore_revision<D: Document + Send>(
+        &self,
+        id: &str,
+        version: i64,
+    ) -> Result<Item<serde_json::Value>, surrealdb::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+            version: i64,
+        }
+
+        let mut result = self
+            .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
+            .query(
+                "LET $restored_inner = (SELECT VALUE inner FROM type::table(string::concat($doc, '_revision')) WHERE meta = $meta_id AND version = $version)[0]",
+            )
+            .query("UPSERT $draft_id SET inner = $restored_inner")
+            .query("UPDATE $meta_id SET draft = $draft_id, modified_at = time::now()")
+            .query("LET $next_version = (SELECT VALUE version FROM type::table(string::concat($doc, '_revision')) WHERE meta = $meta_id ORDER BY version DESC LIMIT 1)[0] ?? 0")
+            .query("CREATE type::table(string::concat($doc, '_revision')) SET meta = $meta_id, version = $next_version + 1, inner = $restored_inner, author = $auth.id")
+            .query(
+                "SELECT
+                id,
+                created_at,
+                modified_at,
+                draft.inner AS inner,
+                published.published_at AS published_at
+            FROM $meta_id
+            FETCH draft, published",
+            )
+            .bind(Bindings {
+                doc: D::identifier().into(),
+                id: id.to_owned().into(),
+                version,
+            })
+            .await?;
+
+        let item: Option<SurrealItem<serde_json::Value>> = result.take(7)?;
+
+        Ok(item
+            .expect("restoring a revision should always return the updated item")
+            .into())
+    }
+}